@@ -1,5 +1,6 @@
 use anyhow::{Context, Result};
-use csv::{Reader, Writer};
+use csv::{ByteRecord, Reader, ReaderBuilder, Terminator, Trim, Writer, WriterBuilder};
+use encoding_rs::{Encoding, UTF_8};
 use encoding_rs_io::DecodeReaderBytes;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
@@ -7,6 +8,45 @@ use std::fs::File;
 use std::io::{Read, Write};
 use std::path::Path;
 
+/// Configurable knobs for reading/writing CSV, mirroring what
+/// `csv::ReaderBuilder`/`csv::WriterBuilder` already expose.
+///
+/// Use `CsvOptions::default()` and override only the fields you need.
+pub struct CsvOptions {
+    pub delimiter: u8,
+    pub has_headers: bool,
+    pub flexible: bool,
+    pub trim: Trim,
+    pub terminator: Terminator,
+    pub quote: u8,
+    pub escape: Option<u8>,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        CsvOptions {
+            delimiter: b',',
+            has_headers: true,
+            flexible: false,
+            trim: Trim::None,
+            terminator: Terminator::CRLF,
+            quote: b'"',
+            escape: None,
+        }
+    }
+}
+
+/// `Terminator::CRLF` means "accept \r\n or \n" to `ReaderBuilder`, which is the correct
+/// `CsvOptions` default for reading, but `WriterBuilder` has no such lenient mode and its own
+/// unconfigured default is a literal `\n`; translate the shared default so that
+/// `save_csv_write_with(w, iter, &CsvOptions::default())` still matches `save_csv_write(w, iter)`.
+fn write_terminator(terminator: Terminator) -> Terminator {
+    match terminator {
+        Terminator::CRLF => Terminator::Any(b'\n'),
+        other => other,
+    }
+}
+
 pub struct CSV;
 
 impl CSV {
@@ -21,7 +61,35 @@ impl CSV {
 
     /// load from str, since str.as_bytes() implemented Read trait
     pub fn load_csv_read<R: Read, D: DeserializeOwned>(read: R) -> Result<Vec<D>> {
-        let mut rdr = Reader::from_reader(read);
+        CSV::load_csv_read_with(read, &CsvOptions::default())
+    }
+
+    /// load from file, with UTF8 BOM detect, using custom delimiter/headers/trim/terminator settings
+    pub fn load_csv_file_with<P: AsRef<Path>, D: DeserializeOwned>(
+        csv: P,
+        opts: &CsvOptions,
+    ) -> Result<Vec<D>> {
+        let path = csv.as_ref();
+        let file = File::open(path).with_context(|| path.display().to_string())?;
+        let v = CSV::load_csv_read_with(DecodeReaderBytes::new(file), opts)
+            .with_context(|| path.display().to_string())?;
+        Ok(v)
+    }
+
+    /// load from str, using custom delimiter/headers/trim/terminator settings
+    pub fn load_csv_read_with<R: Read, D: DeserializeOwned>(
+        read: R,
+        opts: &CsvOptions,
+    ) -> Result<Vec<D>> {
+        let mut rdr = ReaderBuilder::new()
+            .delimiter(opts.delimiter)
+            .has_headers(opts.has_headers)
+            .flexible(opts.flexible)
+            .trim(opts.trim)
+            .terminator(opts.terminator)
+            .quote(opts.quote)
+            .escape(opts.escape)
+            .from_reader(read);
         let mut v = vec![];
         for result in rdr.deserialize::<D>() {
             let record: D = result?;
@@ -30,6 +98,65 @@ impl CSV {
         Ok(v)
     }
 
+    /// stream rows from file one at a time instead of collecting into a Vec, with UTF8 BOM detect
+    pub fn stream_csv_file<P: AsRef<Path>, D: DeserializeOwned>(
+        csv: P,
+    ) -> Result<impl Iterator<Item = Result<D>>> {
+        let path = csv.as_ref();
+        let file = File::open(path).with_context(|| path.display().to_string())?;
+        Ok(CSV::stream_csv_read(DecodeReaderBytes::new(file)))
+    }
+
+    /// stream rows one at a time instead of collecting into a Vec
+    pub fn stream_csv_read<R: Read, D: DeserializeOwned>(read: R) -> impl Iterator<Item = Result<D>> {
+        Reader::from_reader(read)
+            .into_deserialize::<D>()
+            .map(|r| r.map_err(Into::into))
+    }
+
+    /// load rows with a key (from `key_fn`) in `[start, end]`, stopping early once the key
+    /// exceeds `end`; requires `csv` to already be sorted ascending by `key_fn`
+    pub fn load_csv_range<P, D, T, F>(csv: P, start: T, end: T, key_fn: F) -> Result<Vec<D>>
+    where
+        P: AsRef<Path>,
+        D: DeserializeOwned,
+        T: PartialOrd,
+        F: Fn(&D) -> T,
+    {
+        let path = csv.as_ref();
+        let mut v = vec![];
+        for result in CSV::stream_csv_file::<_, D>(path)? {
+            let record = result.with_context(|| path.display().to_string())?;
+            let key = key_fn(&record);
+            if key < start {
+                continue;
+            }
+            if key > end {
+                break;
+            }
+            v.push(record);
+        }
+        Ok(v)
+    }
+
+    /// fast path reusing one `ByteRecord` buffer across the whole read, instead of allocating a
+    /// fresh `StringRecord`/`Vec` per row
+    pub fn for_each_record<R, D, F>(read: R, mut f: F) -> Result<()>
+    where
+        R: Read,
+        D: DeserializeOwned,
+        F: FnMut(&D) -> Result<()>,
+    {
+        let mut rdr = Reader::from_reader(read);
+        let headers = rdr.byte_headers()?.clone();
+        let mut record = ByteRecord::new();
+        while rdr.read_byte_record(&mut record)? {
+            let data: D = record.deserialize(Some(&headers))?;
+            f(&data)?;
+        }
+        Ok(())
+    }
+
     pub fn save_csv_file<'a, P, S: 'a, I>(csv: P, iter: I) -> Result<()>
     where
         P: AsRef<Path>,
@@ -56,6 +183,83 @@ impl CSV {
         wtr.flush()?;
         Ok(wtr)
     }
+
+    /// write, using custom delimiter/headers/flexible/terminator settings
+    pub fn save_csv_write_with<'a, W, S: 'a, I>(
+        write: W,
+        iter: I,
+        opts: &CsvOptions,
+    ) -> Result<Writer<W>>
+    where
+        W: Write,
+        S: Serialize,
+        I: IntoIterator<Item = &'a S>,
+    {
+        let mut wtr = WriterBuilder::new()
+            .delimiter(opts.delimiter)
+            .has_headers(opts.has_headers)
+            .flexible(opts.flexible)
+            .terminator(write_terminator(opts.terminator))
+            .quote(opts.quote)
+            .escape(opts.escape.unwrap_or(b'\\'))
+            .from_writer(write);
+        for record in iter.into_iter() {
+            wtr.serialize(record)?;
+        }
+        wtr.flush()?;
+        Ok(wtr)
+    }
+
+    /// save to file, transcoding the output to `encoding` (e.g. GBK/GB18030) instead of UTF-8;
+    /// `write_bom` additionally emits a UTF-8 BOM when `encoding` is `UTF_8`
+    pub fn save_csv_file_with_encoding<'a, P, S: 'a, I>(
+        csv: P,
+        iter: I,
+        encoding: &'static Encoding,
+        write_bom: bool,
+    ) -> Result<()>
+    where
+        P: AsRef<Path>,
+        S: Serialize,
+        I: IntoIterator<Item = &'a S>,
+    {
+        let path = csv.as_ref();
+        let file = File::create(path).with_context(|| path.display().to_string())?;
+        CSV::save_csv_write_with_encoding(file, iter, encoding, write_bom)
+            .with_context(|| path.display().to_string())?;
+        Ok(())
+    }
+
+    /// write records as usual via `csv::Writer`, then transcode the resulting UTF-8 bytes to
+    /// `encoding` before they hit `write`; default behavior elsewhere stays UTF-8 without BOM
+    pub fn save_csv_write_with_encoding<'a, W, S: 'a, I>(
+        mut write: W,
+        iter: I,
+        encoding: &'static Encoding,
+        write_bom: bool,
+    ) -> Result<()>
+    where
+        W: Write,
+        S: Serialize,
+        I: IntoIterator<Item = &'a S>,
+    {
+        let wtr = CSV::save_csv_write(Vec::new(), iter)?;
+        let utf8 = wtr.into_inner()?;
+        let text = String::from_utf8(utf8).context("csv writer produced invalid utf8")?;
+        if write_bom && encoding == UTF_8 {
+            write.write_all(b"\xEF\xBB\xBF")?;
+        }
+        let (bytes, _, had_errors) = encoding.encode(&text);
+        if had_errors {
+            anyhow::bail!(
+                "csv output contains characters that cannot be represented in {}",
+                encoding.name()
+            );
+        }
+        write.write_all(&bytes)?;
+        write.flush()?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -135,4 +339,134 @@ IF2206,2022-06-06,4068.6,4147.4,4036.2,4144.4,95173,false";
         assert_eq!(barlist[1].test_skip, true);
         Ok(())
     }
+
+    #[test]
+    fn save_csv_write_with_default_matches_save_csv_write() -> Result<()> {
+        let bar = Bar {
+            inst: "IC2206".to_string(),
+            date: NaiveDate::from_ymd(2022, 6, 6),
+            open: 6048.6,
+            high: 6186.4,
+            low: 6031.2,
+            close: 6157.8,
+            volume: 90628,
+            test_skip: true,
+        };
+        let v = vec![&bar];
+        let plain = CSV::save_csv_write(Vec::new(), &v)?.into_inner()?;
+        let with_default = CSV::save_csv_write_with(Vec::new(), &v, &CsvOptions::default())?
+            .into_inner()?;
+        assert_eq!(plain, with_default);
+        Ok(())
+    }
+
+    #[test]
+    fn csv_options_custom_delimiter_round_trips() -> Result<()> {
+        let bar = Bar {
+            inst: "IC2206".to_string(),
+            date: NaiveDate::from_ymd(2022, 6, 6),
+            open: 6048.6,
+            high: 6186.4,
+            low: 6031.2,
+            close: 6157.8,
+            volume: 90628,
+            test_skip: true,
+        };
+        let mut opts = CsvOptions::default();
+        opts.delimiter = b';';
+
+        let v = vec![&bar];
+        let data = String::from_utf8(CSV::save_csv_write_with(Vec::new(), &v, &opts)?.into_inner()?)?;
+        assert!(data.starts_with("inst;date;open;high;low;close;volume;test_skip"));
+
+        let loaded: Vec<Bar> = CSV::load_csv_read_with(data.as_bytes(), &opts)?;
+        assert_eq!(loaded[0].inst, "IC2206");
+        Ok(())
+    }
+
+    #[test]
+    fn stream_csv_read_yields_rows_one_at_a_time() -> Result<()> {
+        let data = "
+inst,date,open,high,low,close,volume,test_skip
+IC2206,2022-06-06,6048.6,6186.4,6031.2,6157.8,90628,false
+IF2206,2022-06-06,4068.6,4147.4,4036.2,4144.4,95173,false";
+
+        let mut iter = CSV::stream_csv_read::<_, Bar>(data.as_bytes());
+        assert_eq!(iter.next().unwrap()?.inst, "IC2206");
+        assert_eq!(iter.next().unwrap()?.inst, "IF2206");
+        assert!(iter.next().is_none());
+        Ok(())
+    }
+
+    #[derive(Debug, Clone, Serialize)]
+    struct Note {
+        text: String,
+    }
+
+    #[test]
+    fn save_csv_write_with_encoding_errors_on_unmappable_chars() {
+        let note = Note {
+            text: "中文".to_string(),
+        };
+        let v = vec![&note];
+        let err = CSV::save_csv_write_with_encoding(
+            Vec::new(),
+            &v,
+            encoding_rs::WINDOWS_1252,
+            false,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("cannot be represented"));
+    }
+
+    #[test]
+    fn load_csv_range_stops_after_end_key() -> Result<()> {
+        let bar = |inst: &str, date: (i32, u32, u32)| Bar {
+            inst: inst.to_string(),
+            date: NaiveDate::from_ymd(date.0, date.1, date.2),
+            open: 1.0,
+            high: 1.0,
+            low: 1.0,
+            close: 1.0,
+            volume: 1,
+            test_skip: true,
+        };
+        let bars = vec![
+            bar("IC2206", (2022, 6, 1)),
+            bar("IC2207", (2022, 6, 6)),
+            bar("IC2208", (2022, 6, 10)),
+            // in range but after a key > end: only skipped if reading stops at IC2208
+            bar("IC2209", (2022, 6, 6)),
+        ];
+        let path = std::env::temp_dir().join("csv_helper_load_csv_range_test.csv");
+        CSV::save_csv_file(&path, &bars)?;
+
+        let result: Vec<Bar> = CSV::load_csv_range(
+            &path,
+            NaiveDate::from_ymd(2022, 6, 5),
+            NaiveDate::from_ymd(2022, 6, 7),
+            |b: &Bar| b.date,
+        )?;
+        std::fs::remove_file(&path)?;
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].inst, "IC2207");
+        Ok(())
+    }
+
+    #[test]
+    fn for_each_record_visits_every_row() -> Result<()> {
+        let data = "
+inst,date,open,high,low,close,volume,test_skip
+IC2206,2022-06-06,6048.6,6186.4,6031.2,6157.8,90628,false
+IF2206,2022-06-06,4068.6,4147.4,4036.2,4144.4,95173,false";
+
+        let mut insts = vec![];
+        CSV::for_each_record(data.as_bytes(), |bar: &Bar| {
+            insts.push(bar.inst.clone());
+            Ok(())
+        })?;
+        assert_eq!(insts, vec!["IC2206".to_string(), "IF2206".to_string()]);
+        Ok(())
+    }
 }